@@ -9,6 +9,7 @@
 //! assert_eq!(cache_control.max_age, Some(Duration::from_secs(60)));
 //! ```
 
+use core::fmt;
 use core::time::Duration;
 
 /// How the data may be cached.
@@ -22,15 +23,29 @@ pub enum Cachability {
 
     /// No one can cache this data.
     NoCache,
+}
 
-    /// Cache the data the first time, and use the cache from then on.
-    OnlyIfCached,
+impl Cachability {
+    /// The directive name this variant serializes to.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Cachability::Public => "public",
+            Cachability::Private => "private",
+            Cachability::NoCache => "no-cache",
+        }
+    }
 }
 
 /// Represents a Cache-Control header
 #[derive(Eq, PartialEq, Debug, Default)]
 pub struct CacheControl {
     pub cachability: Option<Cachability>,
+    /// The specific header fields a `no-cache` directive applies to, if any were given
+    /// (e.g. `no-cache="Set-Cookie"`). `None` means `no-cache` applies to the whole response.
+    pub no_cache_fields: Option<Vec<String>>,
+    /// The specific header fields a `private` directive applies to, if any were given
+    /// (e.g. `private="Set-Cookie"`). `None` means `private` applies to the whole response.
+    pub private_fields: Option<Vec<String>>,
     /// The maximum amount of time a resource is considered fresh.
     /// Unlike `Expires`, this directive is relative to the time of the request.
     pub max_age: Option<Duration>,
@@ -43,6 +58,9 @@ pub struct CacheControl {
     /// Indicates the client wants a response that will still be fresh for at least
     /// the specified number of seconds.
     pub min_fresh: Option<Duration>,
+    /// Indicates the client only wants a cached response, and does not wish to contact the
+    /// origin server at all. A request-only directive (RFC 7234 §5.2.1).
+    pub only_if_cached: bool,
     /// Indicates that once a resource becomes stale, caches do not use their stale
     /// copy without successful validation on the origin server.
     pub must_revalidate: bool,
@@ -53,13 +71,24 @@ pub struct CacheControl {
     pub immutable: bool,
     /// The response may not be stored in _any_ cache.
     pub no_store: bool,
-    /// An intermediate cache or proxy cannot edit the response body, 
+    /// An intermediate cache or proxy cannot edit the response body,
     /// `Content-Encoding`, `Content-Range`, or `Content-Type`.
     pub no_transform: bool,
+    /// Indicates the cache may serve a stale response for this many seconds while it
+    /// revalidates in the background. See [RFC 5861](https://www.rfc-editor.org/rfc/rfc5861).
+    pub stale_while_revalidate: Option<Duration>,
+    /// Indicates the cache may serve a stale response for this many seconds if a revalidation
+    /// request fails. See [RFC 5861](https://www.rfc-editor.org/rfc/rfc5861).
+    pub stale_if_error: Option<Duration>,
+    /// Directives this crate doesn't otherwise model, e.g. vendor extensions like
+    /// `community="UCI"`, kept as `(name, value)` pairs so callers can inspect or re-emit them.
+    pub extensions: Vec<(String, Option<String>)>,
 }
 
 impl CacheControl {
-    /// Parses the value of the Cache-Control header (i.e. everything after "Cache-Control:").
+    /// Parses the value of a response's Cache-Control header (i.e. everything after
+    /// "Cache-Control:"). Equivalent to [`CacheControl::from_value_response`]; kept as the
+    /// default entry point since response headers are the more common case.
     /// ```
     /// use cache_control::{Cachability, CacheControl};
     /// use std::time::Duration;
@@ -69,51 +98,323 @@ impl CacheControl {
     /// assert_eq!(cache_control.max_age, Some(Duration::from_secs(60)));
     /// ```
     pub fn from_value(value: &str) -> Option<Self> {
-        let mut ret = Self::default();
-        for token in value.split(',') {
-            let (key, val) = {
-                let mut split = token.split('=').map(|s| s.trim());
-                (split.next().unwrap(), split.next())
-            };
+        Self::from_value_response(value)
+    }
 
-            match key {
+    /// Parses the value of a response's Cache-Control header, understanding the response-only
+    /// directives (`public`, `private`, `s-maxage`, `must-revalidate`, `proxy-revalidate`,
+    /// `immutable`, `stale-while-revalidate`) described in RFC 7234 §5.2.2, in addition to the
+    /// directives shared with requests.
+    pub fn from_value_response(value: &str) -> Option<Self> {
+        let mut ret = Self::default();
+        for (key, val) in split_directives(value) {
+            match key.as_str() {
                 "public" => ret.cachability = Some(Cachability::Public),
-                "private" => ret.cachability = Some(Cachability::Private),
-                "no-cache" => ret.cachability = Some(Cachability::NoCache),
-                "only-if-cached" => ret.cachability = Some(Cachability::OnlyIfCached),
-                "max-age" => match val.and_then(|v| v.parse().ok()) {
-                    Some(secs) => ret.max_age = Some(Duration::from_secs(secs)),
+                "private" => {
+                    ret.cachability = Some(Cachability::Private);
+                    ret.private_fields = val.as_deref().map(split_field_names);
+                }
+                "s-maxage" => match val.as_deref().and_then(|v| v.parse().ok()) {
+                    Some(secs) => ret.s_max_age = Some(Duration::from_secs(secs)),
                     None => return None,
                 },
-                "max-stale" => match val.and_then(|v| v.parse().ok()) {
+                "must-revalidate" => ret.must_revalidate = true,
+                "proxy-revalidate" => ret.proxy_revalidate = true,
+                "immutable" => ret.immutable = true,
+                "stale-while-revalidate" => match val.as_deref().and_then(|v| v.parse().ok()) {
+                    Some(secs) => ret.stale_while_revalidate = Some(Duration::from_secs(secs)),
+                    None => return None,
+                },
+                _ => {
+                    if !ret.apply_shared_directive(&key, val.as_deref())? {
+                        ret.extensions.push((key, val));
+                    }
+                }
+            };
+        }
+        Some(ret)
+    }
+
+    /// Parses the value of a request's Cache-Control header, understanding the request-only
+    /// directives (`max-stale`, `min-fresh`, `only-if-cached`) described in RFC 7234 §5.2.1, in
+    /// addition to the directives shared with responses.
+    pub fn from_value_request(value: &str) -> Option<Self> {
+        let mut ret = Self::default();
+        for (key, val) in split_directives(value) {
+            match key.as_str() {
+                "max-stale" => match val.as_deref().and_then(|v| v.parse().ok()) {
                     Some(secs) => ret.max_stale = Some(Duration::from_secs(secs)),
                     None => return None,
                 },
-                "min-fresh" => match val.and_then(|v| v.parse().ok()) {
+                "min-fresh" => match val.as_deref().and_then(|v| v.parse().ok()) {
                     Some(secs) => ret.min_fresh = Some(Duration::from_secs(secs)),
                     None => return None,
                 },
-                "must-revalidate" => ret.must_revalidate = true,
-                "proxy-revalidate" => ret.proxy_revalidate = true,
-                "immutable" => ret.immutable = true,
-                "no-store" => ret.no_store = true,
-                "no-transform" => ret.no_transform = true,
-                _ => (),
+                "only-if-cached" => ret.only_if_cached = true,
+                _ => {
+                    if !ret.apply_shared_directive(&key, val.as_deref())? {
+                        ret.extensions.push((key, val));
+                    }
+                }
             };
         }
         Some(ret)
     }
 
-    /// Parses a Cache-Control header.
+    /// Applies a directive shared between requests and responses (`no-cache`, `no-store`,
+    /// `max-age`, `no-transform`, `stale-if-error`). Returns `Some(true)` if `key` was one of
+    /// these and was applied, `Some(false)` if `key` isn't shared (the caller should treat it as
+    /// an extension), or `None` on a malformed value.
+    fn apply_shared_directive(&mut self, key: &str, val: Option<&str>) -> Option<bool> {
+        Some(match key {
+            "no-cache" => {
+                self.cachability = Some(Cachability::NoCache);
+                self.no_cache_fields = val.map(split_field_names);
+                true
+            }
+            "no-store" => {
+                self.no_store = true;
+                true
+            }
+            "no-transform" => {
+                self.no_transform = true;
+                true
+            }
+            "max-age" => match val.and_then(|v| v.parse().ok()) {
+                Some(secs) => {
+                    self.max_age = Some(Duration::from_secs(secs));
+                    true
+                }
+                None => return None,
+            },
+            "stale-if-error" => match val.and_then(|v| v.parse().ok()) {
+                Some(secs) => {
+                    self.stale_if_error = Some(Duration::from_secs(secs));
+                    true
+                }
+                None => return None,
+            },
+            _ => false,
+        })
+    }
+
+    /// Parses a response's Cache-Control header. Equivalent to
+    /// [`CacheControl::from_header_response`].
     pub fn from_header(value: &str) -> Option<Self> {
+        Self::from_header_response(value)
+    }
+
+    /// Parses a response's Cache-Control header, understanding the response-only directives.
+    /// See [`CacheControl::from_value_response`].
+    pub fn from_header_response(value: &str) -> Option<Self> {
         let (name, value) = value.split_once(':')?;
         if !name.trim().eq_ignore_ascii_case("Cache-Control") {
             return None;
         }
-        Self::from_value(value)
+        Self::from_value_response(value)
+    }
+
+    /// Parses a request's Cache-Control header, understanding the request-only directives.
+    /// See [`CacheControl::from_value_request`].
+    pub fn from_header_request(value: &str) -> Option<Self> {
+        let (name, value) = value.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("Cache-Control") {
+            return None;
+        }
+        Self::from_value_request(value)
+    }
+
+    /// Renders this `CacheControl` back into a directive list suitable for the value of a
+    /// Cache-Control header (i.e. everything after "Cache-Control:").
+    /// ```
+    /// use cache_control::{Cachability, CacheControl};
+    /// use std::time::Duration;
+    ///
+    /// let cache_control = CacheControl {
+    ///     cachability: Some(Cachability::Public),
+    ///     max_age: Some(Duration::from_secs(60)),
+    ///     ..CacheControl::default()
+    /// };
+    /// assert_eq!(cache_control.to_value(), "public, max-age=60");
+    /// ```
+    pub fn to_value(&self) -> String {
+        let mut directives = Vec::new();
+
+        if let Some(cachability) = &self.cachability {
+            let fields = match cachability {
+                Cachability::Private => self.private_fields.as_ref(),
+                Cachability::NoCache => self.no_cache_fields.as_ref(),
+                _ => None,
+            };
+            match fields {
+                Some(fields) => {
+                    directives.push(format!("{}=\"{}\"", cachability.as_str(), fields.join(", ")))
+                }
+                None => directives.push(cachability.as_str().to_string()),
+            }
+        }
+        if let Some(max_age) = self.max_age {
+            directives.push(format!("max-age={}", max_age.as_secs()));
+        }
+        if let Some(s_max_age) = self.s_max_age {
+            directives.push(format!("s-maxage={}", s_max_age.as_secs()));
+        }
+        if let Some(max_stale) = self.max_stale {
+            directives.push(format!("max-stale={}", max_stale.as_secs()));
+        }
+        if let Some(min_fresh) = self.min_fresh {
+            directives.push(format!("min-fresh={}", min_fresh.as_secs()));
+        }
+        if self.only_if_cached {
+            directives.push("only-if-cached".to_string());
+        }
+        if self.must_revalidate {
+            directives.push("must-revalidate".to_string());
+        }
+        if self.proxy_revalidate {
+            directives.push("proxy-revalidate".to_string());
+        }
+        if self.immutable {
+            directives.push("immutable".to_string());
+        }
+        if self.no_store {
+            directives.push("no-store".to_string());
+        }
+        if self.no_transform {
+            directives.push("no-transform".to_string());
+        }
+        if let Some(stale_while_revalidate) = self.stale_while_revalidate {
+            directives.push(format!(
+                "stale-while-revalidate={}",
+                stale_while_revalidate.as_secs()
+            ));
+        }
+        if let Some(stale_if_error) = self.stale_if_error {
+            directives.push(format!("stale-if-error={}", stale_if_error.as_secs()));
+        }
+        for (name, val) in &self.extensions {
+            match val {
+                Some(val) => {
+                    directives.push(format!("{}=\"{}\"", name, val.replace('"', "\\\"")))
+                }
+                None => directives.push(name.clone()),
+            }
+        }
+
+        directives.join(", ")
+    }
+
+    /// Renders this `CacheControl` as a full Cache-Control header, including the header name.
+    /// ```
+    /// use cache_control::{Cachability, CacheControl};
+    ///
+    /// let cache_control = CacheControl {
+    ///     cachability: Some(Cachability::Public),
+    ///     ..CacheControl::default()
+    /// };
+    /// assert_eq!(cache_control.to_header(), "Cache-Control: public");
+    /// ```
+    pub fn to_header(&self) -> String {
+        format!("Cache-Control: {}", self.to_value())
+    }
+
+    /// The amount of time a response is considered fresh for, per RFC 7234 §4.2.1: `s-maxage`
+    /// when `shared` is true and it is set, otherwise `max-age`. Returns `None` if neither
+    /// directive is present, in which case freshness must be computed from other sources (e.g.
+    /// the `Expires` header or a heuristic).
+    pub fn freshness_lifetime(&self, shared: bool) -> Option<Duration> {
+        if shared {
+            self.s_max_age.or(self.max_age)
+        } else {
+            self.max_age
+        }
+    }
+
+    /// Whether a response of the given `age` is still fresh, per RFC 7234 §4. `no-store` and
+    /// `no-cache` responses are never servable without revalidation, even if `immutable`;
+    /// otherwise `immutable` responses are always fresh. Returns `None` if none of these
+    /// directives apply and `freshness_lifetime` returns `None`.
+    pub fn is_fresh(&self, age: Duration, shared: bool) -> Option<bool> {
+        if self.no_store || self.cachability == Some(Cachability::NoCache) {
+            return Some(false);
+        }
+        if self.immutable {
+            return Some(true);
+        }
+        let lifetime = self.freshness_lifetime(shared)?;
+        Some(age < lifetime)
+    }
+}
+
+impl fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_value())
+    }
+}
+
+/// Splits a Cache-Control value into `(name, value)` directives, per the RFC 7234 ABNF
+/// `cache-directive = token [ "=" ( token / quoted-string ) ]`. Commas and `=` signs inside a
+/// double-quoted value do not terminate the directive or the key; a `\"` inside a quoted value
+/// escapes a literal quote.
+fn split_directives(value: &str) -> Vec<(String, Option<String>)> {
+    let mut directives = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            '\\' if in_quotes => {
+                current.push(c);
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            ',' if !in_quotes => {
+                directives.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    directives.push(current);
+
+    directives
+        .into_iter()
+        .filter_map(|token| {
+            let token = token.trim();
+            if token.is_empty() {
+                return None;
+            }
+            Some(match token.split_once('=') {
+                Some((key, val)) => (key.trim().to_string(), Some(unquote(val.trim()))),
+                None => (token.to_string(), None),
+            })
+        })
+        .collect()
+}
+
+/// Strips the surrounding double quotes from a quoted-string directive value, if present, and
+/// unescapes any `\"` within it.
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner.replace("\\\"", "\""),
+        None => value.to_string(),
     }
 }
 
+/// Splits the quoted comma-separated field-name list carried by a qualified `no-cache` or
+/// `private` directive (e.g. `no-cache="Set-Cookie, X-Foo"`).
+fn split_field_names(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -150,15 +451,21 @@ mod test {
             *test1,
             CacheControl {
                 cachability: Some(Cachability::NoCache),
+                no_cache_fields: None,
+                private_fields: None,
                 max_age: None,
                 s_max_age: None,
                 max_stale: None,
                 min_fresh: None,
+                only_if_cached: false,
                 must_revalidate: true,
                 proxy_revalidate: false,
                 immutable: false,
                 no_store: true,
                 no_transform: false,
+                stale_while_revalidate: None,
+                stale_if_error: None,
+                extensions: Vec::new(),
             }
         );
     }
@@ -196,16 +503,222 @@ mod test {
             *test1,
             CacheControl {
                 cachability: Some(Cachability::Public),
+                no_cache_fields: None,
+                private_fields: None,
                 max_age: Some(Duration::from_secs(600)),
                 s_max_age: None,
                 max_stale: None,
                 min_fresh: None,
+                only_if_cached: false,
                 must_revalidate: false,
                 proxy_revalidate: false,
                 immutable: false,
                 no_store: false,
                 no_transform: false,
+                stale_while_revalidate: None,
+                stale_if_error: None,
+                extensions: Vec::new(),
             }
         );
     }
+
+    #[test]
+    fn test_round_trip() {
+        for value in [
+            "public, max-age=60",
+            "private, no-transform",
+            "no-cache, must-revalidate",
+            "max-age=600, s-maxage=300, immutable",
+            "private=\"Set-Cookie\", max-age=60",
+            "no-cache=\"Set-Cookie, X-Foo\"",
+            "max-age=600, stale-while-revalidate=30, stale-if-error=300",
+            "private, community=\"UCI\"",
+        ] {
+            let cache_control = CacheControl::from_value(value).unwrap();
+            assert_eq!(
+                CacheControl::from_value(&cache_control.to_value()).unwrap(),
+                cache_control
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_value() {
+        assert_eq!(CacheControl::default().to_value(), "");
+        assert_eq!(
+            CacheControl {
+                cachability: Some(Cachability::Public),
+                max_age: Some(Duration::from_secs(60)),
+                immutable: true,
+                ..CacheControl::default()
+            }
+            .to_value(),
+            "public, max-age=60, immutable"
+        );
+    }
+
+    #[test]
+    fn test_from_value_quoted_field_names() {
+        let test1 = CacheControl::from_value("private, community=\"UCI\"").unwrap();
+        assert_eq!(test1.cachability, Some(Cachability::Private));
+        assert_eq!(test1.private_fields, None);
+
+        let test2 = CacheControl::from_value("no-cache=\"Set-Cookie\"").unwrap();
+        assert_eq!(test2.cachability, Some(Cachability::NoCache));
+        assert_eq!(
+            test2.no_cache_fields,
+            Some(vec!["Set-Cookie".to_string()])
+        );
+
+        let test3 = CacheControl::from_value("private=\"Set-Cookie, X-Foo\", max-age=60").unwrap();
+        assert_eq!(test3.cachability, Some(Cachability::Private));
+        assert_eq!(
+            test3.private_fields,
+            Some(vec!["Set-Cookie".to_string(), "X-Foo".to_string()])
+        );
+        assert_eq!(test3.max_age, Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_from_value_quoted_comma_not_a_separator() {
+        // A comma inside a quoted value must not split the directive list.
+        let test1 = CacheControl::from_value("no-cache=\"Set-Cookie, X-Foo\", no-store").unwrap();
+        assert_eq!(
+            test1.no_cache_fields,
+            Some(vec!["Set-Cookie".to_string(), "X-Foo".to_string()])
+        );
+        assert!(test1.no_store);
+    }
+
+    #[test]
+    fn test_from_value_stale_directives() {
+        let test1 =
+            CacheControl::from_value("max-age=600, stale-while-revalidate=30, stale-if-error=300")
+                .unwrap();
+        assert_eq!(test1.max_age, Some(Duration::from_secs(600)));
+        assert_eq!(
+            test1.stale_while_revalidate,
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(test1.stale_if_error, Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_from_value_extensions() {
+        let test1 = CacheControl::from_value("private, community=\"UCI\"").unwrap();
+        assert_eq!(test1.cachability, Some(Cachability::Private));
+        assert_eq!(
+            test1.extensions,
+            vec![("community".to_string(), Some("UCI".to_string()))]
+        );
+
+        let test2 = CacheControl::from_value("foo, bar=baz").unwrap();
+        assert_eq!(
+            test2.extensions,
+            vec![
+                ("foo".to_string(), None),
+                ("bar".to_string(), Some("baz".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_value_request() {
+        let test1 =
+            CacheControl::from_value_request("no-cache, max-stale=30, only-if-cached").unwrap();
+        assert_eq!(test1.cachability, Some(Cachability::NoCache));
+        assert_eq!(test1.max_stale, Some(Duration::from_secs(30)));
+        assert!(test1.only_if_cached);
+
+        // Response-only directives are not understood in a request context.
+        let test2 = CacheControl::from_value_request("public, max-age=60").unwrap();
+        assert_eq!(test2.cachability, None);
+        assert_eq!(
+            test2.extensions,
+            vec![("public".to_string(), None)]
+        );
+        assert_eq!(test2.max_age, Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_from_value_response_ignores_request_only_directives() {
+        let test1 = CacheControl::from_value_response("public, only-if-cached").unwrap();
+        assert_eq!(test1.cachability, Some(Cachability::Public));
+        assert!(!test1.only_if_cached);
+        assert_eq!(
+            test1.extensions,
+            vec![("only-if-cached".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let cache_control = CacheControl {
+            cachability: Some(Cachability::Private),
+            max_age: Some(Duration::from_secs(60)),
+            ..CacheControl::default()
+        };
+        assert_eq!(cache_control.to_string(), "private, max-age=60");
+    }
+
+    #[test]
+    fn test_freshness_lifetime() {
+        let cache_control = CacheControl {
+            max_age: Some(Duration::from_secs(60)),
+            s_max_age: Some(Duration::from_secs(120)),
+            ..CacheControl::default()
+        };
+        assert_eq!(
+            cache_control.freshness_lifetime(false),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            cache_control.freshness_lifetime(true),
+            Some(Duration::from_secs(120))
+        );
+
+        let no_shared_override = CacheControl {
+            max_age: Some(Duration::from_secs(60)),
+            ..CacheControl::default()
+        };
+        assert_eq!(
+            no_shared_override.freshness_lifetime(true),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(CacheControl::default().freshness_lifetime(false), None);
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let cache_control = CacheControl {
+            max_age: Some(Duration::from_secs(60)),
+            ..CacheControl::default()
+        };
+        assert_eq!(cache_control.is_fresh(Duration::from_secs(30), false), Some(true));
+        assert_eq!(cache_control.is_fresh(Duration::from_secs(90), false), Some(false));
+        assert_eq!(CacheControl::default().is_fresh(Duration::from_secs(0), false), None);
+
+        let immutable = CacheControl {
+            immutable: true,
+            ..CacheControl::default()
+        };
+        assert_eq!(
+            immutable.is_fresh(Duration::from_secs(1_000_000), false),
+            Some(true)
+        );
+
+        let no_store = CacheControl {
+            no_store: true,
+            max_age: Some(Duration::from_secs(60)),
+            ..CacheControl::default()
+        };
+        assert_eq!(no_store.is_fresh(Duration::from_secs(0), false), Some(false));
+
+        let no_cache = CacheControl {
+            cachability: Some(Cachability::NoCache),
+            max_age: Some(Duration::from_secs(60)),
+            ..CacheControl::default()
+        };
+        assert_eq!(no_cache.is_fresh(Duration::from_secs(0), false), Some(false));
+    }
 }